@@ -1,11 +1,12 @@
 use encoding_rs_io::DecodeReaderBytes;
+use flate2::read::MultiGzDecoder;
 use pyo3::prelude::*;
-use pyo3::types::{PyTuple, PyDict};
-use quick_xml::{events::Event, Reader};
+use pyo3::types::{PyTuple, PyDict, PyList};
+use quick_xml::{events::Event, name::ResolveResult, NsReader};
 use std::{
     error::Error,
     fs::File,
-    io::BufReader,
+    io::{BufReader, Cursor, Read},
     str,
     collections::{HashMap},
 };
@@ -14,15 +15,30 @@ const BUF_SIZE: usize = 4096; // 4kb at once
 #[pymodule]
 fn xml_iterator(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(iter_xml, m)?)?;
+    m.add_function(wrap_pyfunction!(iter_xml_from_string, m)?)?;
+    m.add_function(wrap_pyfunction!(iter_xml_from_bytes, m)?)?;
     m.add_function(wrap_pyfunction!(get_edge_counts, m)?)?;
+    m.add_function(wrap_pyfunction!(get_edge_counts_from_string, m)?)?;
+    m.add_function(wrap_pyfunction!(get_edge_counts_from_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_xml, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_records, m)?)?;
     Ok(())
 }
 
 #[pyfunction]
-fn iter_xml(path: &str) -> PyResult<PyObject> {
+#[pyo3(signature = (path, resolve_namespaces = false, compression = None, select = None, max_depth = None))]
+fn iter_xml(
+    path: &str,
+    resolve_namespaces: bool,
+    compression: Option<&str>,
+    select: Option<Vec<String>>,
+    max_depth: Option<usize>,
+) -> PyResult<PyObject> {
     Python::with_gil(|py| -> PyResult<PyObject> {
-        let iterator = get_xml_iterator(path)
+        let mut iterator = get_xml_iterator(path, resolve_namespaces, compression)
             .map_err(|e| pyo3::exceptions::PyIOError::new_err(format!("Failed to open XML file: {}", e)))?;
+        iterator.select = select;
+        iterator.max_depth = max_depth;
         let myiter = PyXMLIterator {
             iter: Box::new(iterator),
         };
@@ -30,93 +46,257 @@ fn iter_xml(path: &str) -> PyResult<PyObject> {
     })
 }
 
-// this was some attempt to kind of do an xmltodict format thing ... but it is quite hard in rust.
-// is there any better to do this algorithmically to avoid some of the issues with rust?
-// Probably better to start with the count things routine which is simple ... just to get motivated about speed.
+#[pyfunction]
+#[pyo3(signature = (data, resolve_namespaces = false))]
+fn iter_xml_from_string(data: String, resolve_namespaces: bool) -> PyResult<PyObject> {
+    Python::with_gil(|py| -> PyResult<PyObject> {
+        let reader: Box<dyn Read + Send> = Box::new(Cursor::new(data.into_bytes()));
+        let iterator = xml_iterator_from_reader(reader, resolve_namespaces);
+        let myiter = PyXMLIterator {
+            iter: Box::new(iterator),
+        };
+        Ok(myiter.into_py(py))
+    })
+}
 
 #[pyfunction]
-fn get_edge_counts(path: &str, n_max: Option<u32>) -> PyResult<PyObject> {
+#[pyo3(signature = (data, resolve_namespaces = false))]
+fn iter_xml_from_bytes(data: Vec<u8>, resolve_namespaces: bool) -> PyResult<PyObject> {
     Python::with_gil(|py| -> PyResult<PyObject> {
-        let iterator = get_xml_iterator(path)
+        let reader: Box<dyn Read + Send> = Box::new(Cursor::new(data));
+        let iterator = xml_iterator_from_reader(reader, resolve_namespaces);
+        let myiter = PyXMLIterator {
+            iter: Box::new(iterator),
+        };
+        Ok(myiter.into_py(py))
+    })
+}
+
+#[pyfunction]
+#[pyo3(signature = (path, n_max = None, resolve_namespaces = false, compression = None))]
+fn get_edge_counts(path: &str, n_max: Option<u32>, resolve_namespaces: bool, compression: Option<&str>) -> PyResult<PyObject> {
+    Python::with_gil(|py| -> PyResult<PyObject> {
+        let iterator = get_xml_iterator(path, resolve_namespaces, compression)
             .map_err(|e| pyo3::exceptions::PyIOError::new_err(format!("Failed to open XML file: {}", e)))?;
-        let mut counter: HashMap<Vec<String>, i32> = HashMap::new();
-        let mut tag_stack: Vec<String> = Vec::new();
-        for (count, event, value) in iterator {
+        edge_counts(py, iterator, n_max)
+    })
+}
+
+#[pyfunction]
+#[pyo3(signature = (data, n_max = None, resolve_namespaces = false))]
+fn get_edge_counts_from_string(data: String, n_max: Option<u32>, resolve_namespaces: bool) -> PyResult<PyObject> {
+    Python::with_gil(|py| -> PyResult<PyObject> {
+        let reader: Box<dyn Read + Send> = Box::new(Cursor::new(data.into_bytes()));
+        let iterator = xml_iterator_from_reader(reader, resolve_namespaces);
+        edge_counts(py, iterator, n_max)
+    })
+}
+
+#[pyfunction]
+#[pyo3(signature = (data, n_max = None, resolve_namespaces = false))]
+fn get_edge_counts_from_bytes(data: Vec<u8>, n_max: Option<u32>, resolve_namespaces: bool) -> PyResult<PyObject> {
+    Python::with_gil(|py| -> PyResult<PyObject> {
+        let reader: Box<dyn Read + Send> = Box::new(Cursor::new(data));
+        let iterator = xml_iterator_from_reader(reader, resolve_namespaces);
+        edge_counts(py, iterator, n_max)
+    })
+}
+
+// shared by get_edge_counts and its from_string/from_bytes siblings now that
+// XMLIterator is built from any Read, not just a file path.
+fn edge_counts(py: Python, iterator: XMLIterator, n_max: Option<u32>) -> PyResult<PyObject> {
+    let mut counter: HashMap<Vec<String>, i32> = HashMap::new();
+    let mut tag_stack: Vec<String> = Vec::new();
+    for (count, event, value, _attrs) in iterator {
+        match event.as_str() {
+            "start" => {
+                tag_stack.push(value.clone());
+                let count = counter.entry(tag_stack.clone()).or_insert(0);
+                *count += 1;
+            }
+            "empty" => {
+                tag_stack.push(value.clone());
+                let count = counter.entry(tag_stack.clone()).or_insert(0);
+                *count += 1;
+                tag_stack.pop();
+            }
+            "text" => {
+            }
+            "end" => {
+                tag_stack.pop();
+            }
+            _ => {panic!("what")}
+        }
+        if let Some(x) = n_max {
+            if count > x { break }
+        }
+    }
+    let counter_out = PyDict::new(py);
+    for (k, v) in counter.into_iter() {
+        let k = PyTuple::new(py, k);
+        let _ = counter_out.set_item(k, v);
+    }
+    Ok(counter_out.into_py(py))
+}
+
+
+// xmltodict-style nested parse: instead of fighting Rust's ownership with a
+// self-referential linked structure, build PyO3 dicts directly as the stream is
+// walked. `stack` holds the currently-open element dicts (index 0 is a synthetic
+// root so the real document root ends up as `{root_tag: {...}}`, matching xmltodict).
+#[pyfunction]
+fn parse_xml(path: &str) -> PyResult<PyObject> {
+    Python::with_gil(|py| -> PyResult<PyObject> {
+        let iterator = get_xml_iterator(path, false, None)
+            .map_err(|e| pyo3::exceptions::PyIOError::new_err(format!("Failed to open XML file: {}", e)))?;
+        let root = PyDict::new(py);
+        let mut stack: Vec<&PyDict> = vec![root];
+        for (_count, event, value, attrs) in iterator {
             match event.as_str() {
-                "start" => {
-                    tag_stack.push(value.clone());
-                    let count = counter.entry(tag_stack.clone()).or_insert(0);
-                    *count += 1;
+                "start" | "empty" => {
+                    let child = PyDict::new(py);
+                    if let Ok(attrs) = attrs.downcast::<PyDict>(py) {
+                        for (k, v) in attrs.iter() {
+                            child.set_item(format!("@{}", k), v)?;
+                        }
+                    }
+                    let parent = *stack.last().unwrap();
+                    match parent.get_item(&value) {
+                        Some(existing) => {
+                            if let Ok(siblings) = existing.downcast::<PyList>() {
+                                siblings.append(child)?;
+                            } else {
+                                let siblings = PyList::new(py, [existing, child]);
+                                parent.set_item(&value, siblings)?;
+                            }
+                        }
+                        None => {
+                            parent.set_item(&value, child)?;
+                        }
+                    }
+                    if event == "start" {
+                        stack.push(child);
+                    }
                 }
                 "text" => {
+                    let top = *stack.last().unwrap();
+                    top.set_item("#text", value)?;
                 }
                 "end" => {
-                    tag_stack.pop();
+                    stack.pop();
                 }
                 _ => {panic!("what")}
             }
-            match n_max {
-                Some(x) => {
-                    if count > x { break }
-                },
-                None => {}
-            }
         }
-        // tuple = PyTuple::new(py, elements);
-        // let counter = PyDict::from_sequence(py, counter.into_py(py));
-        // let counter = counter.into_iter().map(|(k, v)| {(PyTuple::new(py, k), v)}).collect();
-        // let counter = PyDict::from_sequence(counter.iter());
-        let counter_out = PyDict::new(py);
-        for (k, v) in counter.into_iter() {
-            let k = PyTuple::new(py, k);
-            let _ = counter_out.set_item(k, v);
+        Ok(root.into_py(py))
+    })
+}
+
+// Streams a huge XML file and materializes the subtrees rooted at `record_path` into a
+// columnar table (field -> list), the way mbf_gtf turns GTF rows into parallel Vec
+// columns for pandas. `fields` names direct children of the record element whose text
+// should be pulled out, or "@attr"/"child@attr" for attributes on the record root or a
+// named direct child respectively; deeper descendants are not addressable since a bare
+// tag/attribute name can't disambiguate a direct child from a same-named grandchild.
+// Missing fields are filled with None so all columns stay the same length, and repeated
+// string values are interned so low-cardinality columns (the common case for tens of
+// millions of rows) don't duplicate the same PyObject.
+#[pyfunction]
+#[pyo3(signature = (path, record_path, fields, resolve_namespaces = false, compression = None))]
+fn extract_records(
+    path: &str,
+    record_path: Vec<String>,
+    fields: Vec<String>,
+    resolve_namespaces: bool,
+    compression: Option<&str>,
+) -> PyResult<PyObject> {
+    Python::with_gil(|py| -> PyResult<PyObject> {
+        let iterator = get_xml_iterator(path, resolve_namespaces, compression)
+            .map_err(|e| pyo3::exceptions::PyIOError::new_err(format!("Failed to open XML file: {}", e)))?;
+
+        let mut columns: HashMap<String, Vec<Option<String>>> =
+            fields.iter().map(|f| (f.clone(), Vec::new())).collect();
+        let mut tag_stack: Vec<String> = Vec::new();
+        let mut row: Option<HashMap<String, String>> = None;
+        let mut capture_field: Option<(String, usize)> = None;
+
+        for (_count, event, value, attrs) in iterator {
+            let is_open = event == "start" || event == "empty";
+            if is_open {
+                tag_stack.push(value.clone());
+                if row.is_none() && tag_stack == record_path {
+                    row = Some(HashMap::new());
+                }
+                if let Some(cur_row) = row.as_mut() {
+                    let depth = tag_stack.len();
+                    // 0 = the record root itself, 1 = a direct child; only these two
+                    // levels are addressable, since a bare tag/attribute name can't
+                    // disambiguate a direct child from a same-named grandchild.
+                    let rel_depth = depth - record_path.len();
+                    if rel_depth <= 1 {
+                        let prefix = if rel_depth == 0 { String::new() } else { value.clone() };
+                        if let Ok(attrs_dict) = attrs.downcast::<PyDict>(py) {
+                            for (k, v) in attrs_dict.iter() {
+                                let attr_key = format!("{}@{}", prefix, k);
+                                if fields.contains(&attr_key) {
+                                    let v_str: String = v.extract()?;
+                                    cur_row.insert(attr_key, v_str);
+                                }
+                            }
+                        }
+                    }
+                    if rel_depth == 1 && fields.contains(&value) && capture_field.is_none() {
+                        capture_field = Some((value.clone(), depth));
+                    }
+                }
+            }
+            if event == "text" {
+                if let (Some(cur_row), Some((field, _))) = (row.as_mut(), &capture_field) {
+                    cur_row
+                        .entry(field.clone())
+                        .and_modify(|s| { s.push(' '); s.push_str(&value); })
+                        .or_insert(value);
+                }
+            }
+            if event == "end" || event == "empty" {
+                if let Some((_, cap_depth)) = capture_field {
+                    if tag_stack.len() == cap_depth {
+                        capture_field = None;
+                    }
+                }
+                if row.is_some() && tag_stack == record_path {
+                    let cur_row = row.take().unwrap();
+                    for f in &fields {
+                        columns.get_mut(f).unwrap().push(cur_row.get(f).cloned());
+                    }
+                }
+                tag_stack.pop();
+            }
         }
-        Ok(counter_out.into_py(py))
 
+        let mut interned: HashMap<String, PyObject> = HashMap::new();
+        let out = PyDict::new(py);
+        for field in &fields {
+            let list = PyList::empty(py);
+            for v in &columns[field] {
+                match v {
+                    Some(s) => {
+                        let obj = interned
+                            .entry(s.clone())
+                            .or_insert_with(|| s.into_py(py))
+                            .clone_ref(py);
+                        list.append(obj)?;
+                    }
+                    None => list.append(py.None())?,
+                }
+            }
+            out.set_item(field, list)?;
+        }
+        Ok(out.into_py(py))
     })
 }
 
-
-// struct NestedThing {
-//     x: LinkedList<HashMap<String, NestedThing>>,
-// }
-
-// #[pyfunction]
-// fn read_xml(path: &str) -> PyResult<PyObject> {
-//     // see https://stackoverflow.com/questions/59640315/how-do-i-define-a-nested-hashmap-with-an-unknown-nesting-level 
-//     Python::with_gil(|py| -> PyResult<PyObject> {
-//         let iterator = get_xml_iterator(path).unwrap();
-//         // let mut d = HashMap::new();
-//         let out = NestedThing{x: LinkedList::new()};
-//         // let mut back = NestedThing{x: LinkedList::new()};
-//         let back: LinkedList<NestedThing> = LinkedList::new();  // this is just a stack
-//         let cur = out;
-//         for (count, event, value) in iterator {
-//             match event.as_str() {
-//                 "start" => {
-//                     let entry = HashMap::from([(value, NestedThing{x: LinkedList::new()})]);
-//                     cur.x.push_back(entry);
-//                     // back.push_back(cur);
-//                     // let cur = back.back().unwrap().back().unwrap().entry(value);
-//                 }
-//                 "text" => {
-//                     // cur.push_back(
-//                     //     HashMap::from(
-//                     //         [("text".to_string(), LinkedList::from([value]))]
-//                     //     )
-//                     // );
-//                 }
-//                 "end" => {
-//                     // let cur = back.pop_back().unwrap();
-//                 }
-//                 _ => {panic!("what")}
-//             }
-//         }
-//         Ok("asdf".into_py(py))
-//     })
-// }
-
-type ItemType = (u32, String, String);
+type ItemType = (u32, String, String, PyObject);
 
 #[pyclass]
 struct PyXMLIterator {
@@ -140,30 +320,117 @@ impl PyXMLIterator {
     }
 }
 
+// builds a {name: value} dict of unescaped attributes for Start/Empty events;
+// malformed attributes (bad escaping, non-UTF8) are skipped rather than aborting the event.
+// `xmlns`/`xmlns:prefix` namespace declarations are skipped since they are not data
+// attributes. Keys are always the local name, also when `resolve_namespaces` is on
+// (`resolve_name` applies to tag names only), so distinctly-prefixed attributes that share
+// a local name, e.g. `a:id` and `b:id`, collapse onto the same dict key and the later one wins.
+fn attributes_dict(py: Python, e: &quick_xml::events::BytesStart) -> PyObject {
+    let dict = PyDict::new(py);
+    for attr in e.attributes() {
+        let attr = match attr {
+            Ok(attr) => attr,
+            Err(_) => continue,
+        };
+        let raw_key = attr.key.as_ref();
+        if raw_key == b"xmlns" || raw_key.starts_with(b"xmlns:") {
+            continue;
+        }
+        let key = match str::from_utf8(attr.key.local_name().into_inner()) {
+            Ok(key) => key.to_string(),
+            Err(_) => continue,
+        };
+        let value = match attr.unescape_value() {
+            Ok(value) => value.into_owned(),
+            Err(_) => continue,
+        };
+        let _ = dict.set_item(key, value);
+    }
+    dict.into_py(py)
+}
+
+// resolves a local tag name against its namespace when resolution is requested, using
+// Clark notation ("{uri}local"); unbound prefixes become a recognizable "{unknown}local"
+// marker instead of panicking, since ResolveResult::Unknown is a normal outcome.
+fn resolve_name(res: ResolveResult, local: &str, resolve_namespaces: bool) -> String {
+    if !resolve_namespaces {
+        local.to_string()
+    } else {
+        match res {
+            ResolveResult::Bound(ns) => {
+                let uri = str::from_utf8(ns.into_inner()).unwrap_or("");
+                format!("{{{}}}{}", uri, local)
+            }
+            ResolveResult::Unbound => local.to_string(),
+            ResolveResult::Unknown(_) => format!("{{unknown}}{}", local),
+        }
+    }
+}
+
 struct XMLIterator {
-    reader: Reader<BufReader<DecodeReaderBytes<File, Vec<u8>>>>,
+    reader: NsReader<BufReader<DecodeReaderBytes<Box<dyn Read + Send>, Vec<u8>>>>,
     count: u32,
+    resolve_namespaces: bool,
+    tag_stack: Vec<String>,
+    select: Option<Vec<String>>,
+    max_depth: Option<usize>,
+}
+
+impl XMLIterator {
+    // true once `tag_stack` has `select` as a prefix (always true when `select` is
+    // unset), and, when `max_depth` is set, while the depth below `select` is still
+    // within bounds; lets callers stream just a region of interest out of a huge document.
+    fn in_selection(&self) -> bool {
+        match &self.select {
+            None => true,
+            Some(select) => {
+                let has_prefix = self.tag_stack.len() >= select.len()
+                    && &self.tag_stack[..select.len()] == select.as_slice();
+                has_prefix
+                    && match self.max_depth {
+                        Some(max_depth) => self.tag_stack.len() - select.len() <= max_depth,
+                        None => true,
+                    }
+            }
+        }
+    }
 }
 
 impl Iterator for XMLIterator {
-    type Item = (u32, String, String);
+    type Item = ItemType;
     fn next(&mut self) -> Option<Self::Item> {
-        /* NOTE: this ingored attribute values see below if you need that */
         let mut buf: Vec<u8> = Vec::with_capacity(BUF_SIZE);
         self.count += 1;
         loop {
-            match self.reader.read_event_into(&mut buf).ok()? {
+            let (ns, event) = self.reader.read_resolved_event_into(&mut buf).ok()?;
+            match event {
                 Event::Start(e) => {
-                    let value = str::from_utf8(e.local_name().into_inner()).ok()?.to_string();
-                    break Some((self.count - 1, "start".to_string(), value))
+                    let local = str::from_utf8(e.local_name().into_inner()).ok()?;
+                    let value = resolve_name(ns, local, self.resolve_namespaces);
+                    self.tag_stack.push(value.clone());
+                    if !self.in_selection() { continue }
+                    let attrs = Python::with_gil(|py| attributes_dict(py, &e));
+                    break Some((self.count - 1, "start".to_string(), value, attrs))
                 }
                 Event::End(e) => {
-                    let value = str::from_utf8(e.local_name().into_inner()).ok()?.to_string();
-                    break Some((self.count - 1, "end".to_string(), value))
+                    let local = str::from_utf8(e.local_name().into_inner()).ok()?;
+                    let value = resolve_name(ns, local, self.resolve_namespaces);
+                    let yield_this = self.in_selection();
+                    self.tag_stack.pop();
+                    if !yield_this { continue }
+                    let attrs = Python::with_gil(|py| PyDict::new(py).into_py(py));
+                    break Some((self.count - 1, "end".to_string(), value, attrs))
                 }
                 Event::Empty(e) => {
-                    let value = str::from_utf8(e.local_name().into_inner()).ok()?.to_string();
-                    break Some((self.count - 1, "empty".to_string(), value))
+                    let local = str::from_utf8(e.local_name().into_inner()).ok()?;
+                    let value = resolve_name(ns, local, self.resolve_namespaces);
+                    self.tag_stack.push(value.clone());
+                    let yield_this = self.in_selection();
+                    self.tag_stack.pop();
+                    if !yield_this { continue }
+                    let attrs = Python::with_gil(|py| attributes_dict(py, &e));
+                    break Some((self.count - 1, "empty".to_string(), value, attrs))
                 }
                 Event::Text(e) => {
                     let value = match e.unescape() {
@@ -171,7 +438,9 @@ impl Iterator for XMLIterator {
                         Err(_) => continue, // Skip invalid text content
                     };
                     if value == "" { continue }
-                    break Some((self.count - 1, "text".to_string(), value))
+                    if !self.in_selection() { continue }
+                    let attrs = Python::with_gil(|py| PyDict::new(py).into_py(py));
+                    break Some((self.count - 1, "text".to_string(), value, attrs))
                 }
                 Event::Eof => {
                     break None
@@ -183,11 +452,218 @@ impl Iterator for XMLIterator {
 }
 
 
-fn get_xml_iterator(path: &str) -> Result<XMLIterator, Box<dyn Error>> {
-    println!("xml_iterator::reading {:?}", path);
+// gzip is detected either from an explicit `compression="gzip"` argument or from a
+// `.gz` file extension; MultiGzDecoder is used (rather than GzDecoder) so concatenated
+// gzip members, as produced by some dump generators, are decompressed in full.
+fn open_input(path: &str, compression: Option<&str>) -> Result<Box<dyn Read + Send>, Box<dyn Error>> {
     let fin = File::open(path)?;
-    let bufreader = BufReader::new(DecodeReaderBytes::new(fin));
-    let reader = Reader::from_reader(bufreader);
-    let reader_iter = XMLIterator {reader: reader, count: 0};
-    Ok(reader_iter)
+    let is_gzip = match compression {
+        Some("gzip") => true,
+        Some("none") => false,
+        Some(other) => return Err(format!("unknown compression: {:?}", other).into()),
+        None => path.ends_with(".gz"),
+    };
+    if is_gzip {
+        Ok(Box::new(MultiGzDecoder::new(fin)))
+    } else {
+        Ok(Box::new(fin))
+    }
+}
+
+fn get_xml_iterator(path: &str, resolve_namespaces: bool, compression: Option<&str>) -> Result<XMLIterator, Box<dyn Error>> {
+    println!("xml_iterator::reading {:?}", path);
+    let fin = open_input(path, compression)?;
+    Ok(xml_iterator_from_reader(fin, resolve_namespaces))
+}
+
+// shared by the path-based, from_string and from_bytes constructors: any `Read` can be
+// wrapped the same way, keeping DecodeReaderBytes encoding detection on every path so
+// non-UTF-8 input still works regardless of where the bytes came from.
+fn xml_iterator_from_reader(reader: Box<dyn Read + Send>, resolve_namespaces: bool) -> XMLIterator {
+    let bufreader = BufReader::new(DecodeReaderBytes::new(reader));
+    let reader = NsReader::from_reader(bufreader);
+    XMLIterator {
+        reader,
+        count: 0,
+        resolve_namespaces,
+        tag_stack: Vec::new(),
+        select: None,
+        max_depth: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn attributes_are_emitted_and_xmlns_is_excluded() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let obj = iter_xml_from_string(
+                "<root xmlns:h=\"http://example.com\" id=\"1\" h:extra=\"2\"></root>".to_string(),
+                false,
+            ).unwrap();
+            let first = obj.as_ref(py).iter().unwrap().next().unwrap().unwrap();
+            let (_, event, tag, attrs): (u32, String, String, PyObject) = first.extract().unwrap();
+            assert_eq!(event, "start");
+            assert_eq!(tag, "root");
+            let attrs: &PyDict = attrs.downcast(py).unwrap();
+            assert_eq!(attrs.len(), 2);
+            assert!(attrs.get_item("id").is_some());
+            assert!(attrs.get_item("extra").is_some());
+            assert!(attrs.get_item("xmlns:h").is_none());
+            assert!(attrs.get_item("h").is_none());
+        });
+    }
+
+    #[test]
+    fn namespace_resolution_uses_clark_notation_and_unknown_marker() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let xml = "<root xmlns:a=\"urn:a\"><a:item/><b:item/></root>".to_string();
+            let obj = iter_xml_from_string(xml, true).unwrap();
+            let mut tags = Vec::new();
+            for item in obj.as_ref(py).iter().unwrap() {
+                let (_, event, tag, _): (u32, String, String, PyObject) = item.unwrap().extract().unwrap();
+                if event == "start" || event == "empty" {
+                    tags.push(tag);
+                }
+            }
+            assert_eq!(tags[0], "root");
+            assert_eq!(tags[1], "{urn:a}item");
+            assert_eq!(tags[2], "{unknown}item");
+        });
+    }
+
+    #[test]
+    fn parse_xml_promotes_repeated_siblings_to_a_list() {
+        pyo3::prepare_freethreaded_python();
+        let path = std::env::temp_dir().join("xml_iterator_test_parse_xml.xml");
+        std::fs::write(&path, b"<root><item>a</item><item>b</item></root>").unwrap();
+        Python::with_gil(|py| {
+            let obj = parse_xml(path.to_str().unwrap()).unwrap();
+            let root: &PyDict = obj.downcast(py).unwrap();
+            let inner: &PyDict = root.get_item("root").unwrap().downcast().unwrap();
+            let items: &PyList = inner.get_item("item").unwrap().downcast().unwrap();
+            assert_eq!(items.len(), 2);
+            let first: &PyDict = items.get_item(0).downcast().unwrap();
+            let second: &PyDict = items.get_item(1).downcast().unwrap();
+            assert_eq!(first.get_item("#text").unwrap().extract::<String>().unwrap(), "a");
+            assert_eq!(second.get_item("#text").unwrap().extract::<String>().unwrap(), "b");
+        });
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn iter_xml_transparently_decompresses_gzip_input() {
+        pyo3::prepare_freethreaded_python();
+        let path = std::env::temp_dir().join("xml_iterator_test_gzip.xml.gz");
+        {
+            let file = std::fs::File::create(&path).unwrap();
+            let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            encoder.write_all(b"<root><a/></root>").unwrap();
+            encoder.finish().unwrap();
+        }
+        Python::with_gil(|py| {
+            let obj = iter_xml(path.to_str().unwrap(), false, None, None, None).unwrap();
+            let first = obj.as_ref(py).iter().unwrap().next().unwrap().unwrap();
+            let (_, event, tag, _): (u32, String, String, PyObject) = first.extract().unwrap();
+            assert_eq!(event, "start");
+            assert_eq!(tag, "root");
+        });
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn extract_records_fills_missing_fields_with_none() {
+        pyo3::prepare_freethreaded_python();
+        let path = std::env::temp_dir().join("xml_iterator_test_extract.xml");
+        std::fs::write(&path, b"<Root><Rec><a>1</a><b>2</b></Rec><Rec><a>3</a></Rec></Root>").unwrap();
+        Python::with_gil(|py| {
+            let obj = extract_records(
+                path.to_str().unwrap(),
+                vec!["Root".to_string(), "Rec".to_string()],
+                vec!["a".to_string(), "b".to_string()],
+                false,
+                None,
+            ).unwrap();
+            let out: &PyDict = obj.downcast(py).unwrap();
+            let a: &PyList = out.get_item("a").unwrap().downcast().unwrap();
+            let b: &PyList = out.get_item("b").unwrap().downcast().unwrap();
+            assert_eq!(a.len(), 2);
+            assert_eq!(b.len(), 2);
+            assert_eq!(a.get_item(0).extract::<String>().unwrap(), "1");
+            assert_eq!(a.get_item(1).extract::<String>().unwrap(), "3");
+            assert_eq!(b.get_item(0).extract::<String>().unwrap(), "2");
+            assert!(b.get_item(1).is_none());
+        });
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn iter_xml_select_scopes_to_subtree_and_max_depth_bounds_it() {
+        pyo3::prepare_freethreaded_python();
+        let path = std::env::temp_dir().join("xml_iterator_test_select.xml");
+        std::fs::write(&path, b"<root><keep><a><b>x</b></a></keep><skip><a/></skip></root>").unwrap();
+        Python::with_gil(|py| {
+            let obj = iter_xml(
+                path.to_str().unwrap(),
+                false,
+                None,
+                Some(vec!["root".to_string(), "keep".to_string()]),
+                Some(1),
+            ).unwrap();
+            let mut tags = Vec::new();
+            for item in obj.as_ref(py).iter().unwrap() {
+                let (_, event, tag, _): (u32, String, String, PyObject) = item.unwrap().extract().unwrap();
+                if event == "start" {
+                    tags.push(tag);
+                }
+            }
+            assert!(tags.contains(&"keep".to_string()));
+            assert!(tags.contains(&"a".to_string()));
+            assert!(!tags.contains(&"b".to_string()));
+            assert!(!tags.contains(&"skip".to_string()));
+        });
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn iter_xml_from_string_and_from_bytes_agree() {
+        pyo3::prepare_freethreaded_python();
+        let xml = "<root><a>1</a><a>2</a></root>".to_string();
+        Python::with_gil(|py| {
+            let from_string = iter_xml_from_string(xml.clone(), false).unwrap();
+            let from_bytes = iter_xml_from_bytes(xml.into_bytes(), false).unwrap();
+            let collect = |obj: PyObject| -> Vec<String> {
+                obj.as_ref(py)
+                    .iter()
+                    .unwrap()
+                    .map(|item| {
+                        let (_, event, tag, _): (u32, String, String, PyObject) =
+                            item.unwrap().extract().unwrap();
+                        format!("{}:{}", event, tag)
+                    })
+                    .collect()
+            };
+            assert_eq!(collect(from_string), collect(from_bytes));
+        });
+    }
+
+    // regression test for a reviewer-reported bug: edge_counts used to panic on any
+    // self-closing tag instead of counting it, which meant the brand-new
+    // get_edge_counts_from_string/_from_bytes entry points aborted on trivially valid XML.
+    #[test]
+    fn get_edge_counts_from_string_handles_self_closing_tags() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let counts = get_edge_counts_from_string("<r><a/><a/></r>".to_string(), None, false).unwrap();
+            let counts: &PyDict = counts.downcast(py).unwrap();
+            let key = PyTuple::new(py, ["r".to_string(), "a".to_string()]);
+            let count: i32 = counts.get_item(key).unwrap().extract().unwrap();
+            assert_eq!(count, 2);
+        });
+    }
 }